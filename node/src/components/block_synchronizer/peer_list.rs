@@ -1,20 +1,276 @@
-use std::collections::{btree_map::Entry, BTreeMap};
+use std::{
+    collections::{btree_map::Entry, hash_map::DefaultHasher, BTreeMap, BTreeSet},
+    hash::{Hash, Hasher},
+    net::SocketAddr,
+};
 
 use datasize::DataSize;
 use itertools::Itertools;
-use rand::seq::IteratorRandom;
+use rand::{
+    seq::{IteratorRandom, SliceRandom},
+    Rng,
+};
 use tracing::debug;
 
-use crate::{types::NodeId, NodeRng};
+use crate::{components::network::gossiped_address::NodeCapabilities, types::NodeId, NodeRng};
 use casper_types::{TimeDiff, Timestamp};
 
-#[derive(Copy, Clone, PartialEq, Eq, DataSize, Debug, Default)]
-enum PeerQuality {
-    #[default]
-    Unknown,
-    Unreliable,
-    Reliable,
-    Dishonest,
+/// Reward added to a peer's reputation score for a successful interaction.
+const REPUTATION_REWARD: f64 = 1.0;
+/// Penalty subtracted from a peer's reputation score for a failed interaction.
+///
+/// Larger in magnitude than `REPUTATION_REWARD` so that misbehavior is costlier than good
+/// behavior is rewarding.
+const REPUTATION_PENALTY: f64 = 2.0;
+/// The score a peer's reputation decays towards over time.
+const REPUTATION_BASELINE: f64 = 0.0;
+/// The score pinned to a peer once it has been marked dishonest.
+const REPUTATION_BANNED: f64 = f64::MIN;
+/// The time, in seconds, it takes for a peer's reputation to decay halfway back to the baseline.
+const REPUTATION_HALF_LIFE_SECS: f64 = 600.0;
+/// The smoothing factor applied to each new latency sample folded into a peer's EWMA.
+const LATENCY_EWMA_ALPHA: f64 = 0.2;
+/// Added to a peer's EWMA latency before inverting it into a selection weight, so that
+/// near-zero latencies don't produce a weight dominating every other candidate.
+const LATENCY_WEIGHT_CONSTANT_MILLIS: f64 = 50.0;
+/// Width of a reliability tier used to group peers before the latency-weighted draw.
+///
+/// Peers decay continuously with wall-clock time, so comparing `decayed_score` for exact
+/// equality would only ever group peers that have never been rewarded or penalized. Rounding
+/// down to a tier of this width instead groups peers that are, for selection purposes, equally
+/// reliable, and lets the latency-weighted draw actually discriminate within that group.
+const RELIABILITY_TIER_WIDTH: f64 = 1.0;
+/// Size of the Sybil-resistant sampling view, expressed as a multiple of `max_simultaneous_peers`.
+const SAMPLING_VIEW_SLOTS_PER_PEER: u32 = 4;
+/// Fraction of sampling-view slot seeds re-randomized on each `peer_refresh_interval` tick, to
+/// churn the view and recover from a transient eclipse.
+const SAMPLING_VIEW_CHURN_FRACTION: f64 = 0.1;
+/// Maximum number of observed addresses retained per peer, evicting the least-recently-seen
+/// once exceeded.
+const MAX_ADDRESSES_PER_PEER: usize = 3;
+
+/// Buckets a decayed reputation score into a discrete reliability tier, so peers that are
+/// close enough in score to be considered equally reliable group together for the
+/// latency-weighted draw instead of requiring bit-for-bit equal scores.
+fn reliability_tier(score: f64) -> i64 {
+    (score / RELIABILITY_TIER_WIDTH).floor() as i64
+}
+
+/// Hashes a slot's seed together with a candidate `NodeId`.
+///
+/// The slot occupant is whichever candidate minimizes this hash: since the seed is unknown to
+/// candidates in advance, an attacker flooding `register_peer` with many `NodeId`s can only win
+/// slots in proportion to its true share of the candidate pool, not by choice.
+fn slot_hash(seed: u64, node_id: &NodeId) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    seed.hash(&mut hasher);
+    node_id.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A single slot in the sampling view: whichever known candidate minimizes `slot_hash(seed, _)`
+/// occupies it.
+#[derive(Clone, PartialEq, DataSize, Debug)]
+struct SamplingSlot {
+    seed: u64,
+    occupant: Option<NodeId>,
+}
+
+impl SamplingSlot {
+    fn new(rng: &mut NodeRng) -> Self {
+        SamplingSlot {
+            seed: rng.gen(),
+            occupant: None,
+        }
+    }
+
+    fn recompute(&mut self, candidates: impl Iterator<Item = NodeId>) {
+        self.occupant = candidates.min_by_key(|candidate| slot_hash(self.seed, candidate));
+    }
+
+    /// Assigns `candidate` into this slot if it would win, without a full recompute.
+    fn consider_candidate(&mut self, candidate: NodeId) {
+        let candidate_hash = slot_hash(self.seed, &candidate);
+        let should_replace = match self.occupant {
+            None => true,
+            Some(occupant) => candidate_hash < slot_hash(self.seed, &occupant),
+        };
+        if should_replace {
+            self.occupant = Some(candidate);
+        }
+    }
+}
+
+/// A Byzantine-resilient peer-sampling view: a fixed number of slots, each independently and
+/// uniformly selecting one candidate, so an adversary registering a flood of `NodeId`s cannot
+/// dominate peer selection beyond its true share of the candidate pool.
+#[derive(Clone, PartialEq, DataSize, Debug)]
+struct SamplingView {
+    slots: Vec<SamplingSlot>,
+}
+
+impl SamplingView {
+    fn new(num_slots: usize, rng: &mut NodeRng) -> Self {
+        SamplingView {
+            slots: (0..num_slots).map(|_| SamplingSlot::new(rng)).collect(),
+        }
+    }
+
+    fn consider_candidate(&mut self, candidate: NodeId) {
+        for slot in &mut self.slots {
+            slot.consider_candidate(candidate);
+        }
+    }
+
+    /// Drops `evicted` from every slot it occupies, recomputing those slots from the remaining
+    /// candidates.
+    fn evict(&mut self, evicted: NodeId, remaining_candidates: &[NodeId]) {
+        for slot in &mut self.slots {
+            if slot.occupant == Some(evicted) {
+                slot.recompute(remaining_candidates.iter().copied());
+            }
+        }
+    }
+
+    /// Re-randomizes a churn fraction of slot seeds and recomputes them against the current
+    /// candidate pool, so a stale eclipse doesn't persist indefinitely.
+    fn churn(&mut self, candidates: &[NodeId], rng: &mut NodeRng) {
+        let churn_count =
+            ((self.slots.len() as f64) * SAMPLING_VIEW_CHURN_FRACTION).ceil() as usize;
+        let indices = (0..self.slots.len()).choose_multiple(rng, churn_count);
+        for index in indices {
+            self.slots[index].seed = rng.gen();
+            self.slots[index].recompute(candidates.iter().copied());
+        }
+    }
+
+    fn occupants(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.slots.iter().filter_map(|slot| slot.occupant)
+    }
+}
+
+/// An endpoint observed for a peer, and when it was last seen to be current.
+#[derive(Clone, Copy, PartialEq, DataSize, Debug)]
+struct PeerAddress {
+    addr: SocketAddr,
+    last_seen: Timestamp,
+    /// When this address was last handed out as a retry candidate. `None` if it has never been
+    /// handed out, or if it's since been reconfirmed by a fresh `last_seen`.
+    last_attempted: Option<Timestamp>,
+}
+
+#[derive(Clone, PartialEq, DataSize, Debug)]
+struct PeerReputation {
+    score: f64,
+    last_updated: Timestamp,
+    banned: bool,
+    /// Exponentially-weighted moving average of observed response times, in milliseconds.
+    /// `None` until the peer has answered at least one request.
+    latency_ewma_millis: Option<f64>,
+    /// Capabilities this peer has advertised via gossiped address messages or direct handshake.
+    capabilities: NodeCapabilities,
+    /// Endpoints this peer has been observed at, bounded to `MAX_ADDRESSES_PER_PEER`.
+    addresses: Vec<PeerAddress>,
+}
+
+impl PeerReputation {
+    fn new() -> Self {
+        PeerReputation {
+            score: REPUTATION_BASELINE,
+            last_updated: Timestamp::now(),
+            banned: false,
+            latency_ewma_millis: None,
+            capabilities: NodeCapabilities::none(),
+            addresses: Vec::new(),
+        }
+    }
+
+    /// Records or refreshes an observed endpoint, evicting the least-recently-seen address if
+    /// the peer is already at capacity.
+    fn register_address(&mut self, addr: SocketAddr) {
+        let now = Timestamp::now();
+        if let Some(existing) = self.addresses.iter_mut().find(|entry| entry.addr == addr) {
+            existing.last_seen = now;
+            existing.last_attempted = None;
+            return;
+        }
+        if self.addresses.len() >= MAX_ADDRESSES_PER_PEER {
+            if let Some((lru_index, _)) = self
+                .addresses
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, entry)| entry.last_seen)
+            {
+                self.addresses.remove(lru_index);
+            }
+        }
+        self.addresses.push(PeerAddress {
+            addr,
+            last_seen: now,
+            last_attempted: None,
+        });
+    }
+
+    /// Folds a new response-time sample into the peer's latency EWMA.
+    fn register_response_time(&mut self, response_time: TimeDiff) {
+        let sample_millis = response_time.millis() as f64;
+        self.latency_ewma_millis = Some(match self.latency_ewma_millis {
+            Some(ewma) => LATENCY_EWMA_ALPHA * sample_millis + (1.0 - LATENCY_EWMA_ALPHA) * ewma,
+            None => sample_millis,
+        });
+    }
+
+    /// Selection weight favoring low-latency peers: the inverse of the EWMA latency (or a
+    /// neutral weight for peers with no latency samples yet).
+    fn selection_weight(&self) -> f64 {
+        match self.latency_ewma_millis {
+            Some(ewma) => 1.0 / (ewma + LATENCY_WEIGHT_CONSTANT_MILLIS),
+            None => 1.0 / LATENCY_WEIGHT_CONSTANT_MILLIS,
+        }
+    }
+
+    /// Returns the current, decayed score without mutating `self`.
+    fn decayed_score(&self, now: Timestamp) -> f64 {
+        if self.banned {
+            return REPUTATION_BANNED;
+        }
+        let elapsed_secs = now.saturating_diff(self.last_updated).millis() as f64 / 1000.0;
+        let decay = 0.5f64.powf(elapsed_secs / REPUTATION_HALF_LIFE_SECS);
+        REPUTATION_BASELINE + (self.score - REPUTATION_BASELINE) * decay
+    }
+
+    /// Applies decay up to `now` and folds it into the stored score.
+    fn decay(&mut self, now: Timestamp) {
+        if self.banned {
+            return;
+        }
+        self.score = self.decayed_score(now);
+        self.last_updated = now;
+    }
+
+    fn reward(&mut self) {
+        if self.banned {
+            return;
+        }
+        let now = Timestamp::now();
+        self.decay(now);
+        self.score += REPUTATION_REWARD;
+    }
+
+    fn penalize(&mut self) {
+        if self.banned {
+            return;
+        }
+        let now = Timestamp::now();
+        self.decay(now);
+        self.score -= REPUTATION_PENALTY;
+    }
+
+    fn ban(&mut self) {
+        self.banned = true;
+        self.score = REPUTATION_BANNED;
+        self.last_updated = Timestamp::now();
+    }
 }
 
 pub(super) enum PeersStatus {
@@ -23,36 +279,62 @@ pub(super) enum PeersStatus {
     Stale,
 }
 
-#[derive(Clone, PartialEq, Eq, DataSize, Debug)]
+#[derive(Clone, PartialEq, DataSize, Debug)]
 pub(super) struct PeerList {
-    peer_list: BTreeMap<NodeId, PeerQuality>,
+    peer_list: BTreeMap<NodeId, PeerReputation>,
+    sampling_view: SamplingView,
     keep_fresh: Timestamp,
     max_simultaneous_peers: u32,
     peer_refresh_interval: TimeDiff,
+    /// How long an address must have gone unseen before it becomes a retry candidate.
+    address_retry_interval: TimeDiff,
+    /// How long an address may go unseen before it's dropped entirely.
+    address_max_age: TimeDiff,
 }
 
 impl PeerList {
-    pub(super) fn new(max_simultaneous_peers: u32, peer_refresh_interval: TimeDiff) -> Self {
+    pub(super) fn new(
+        max_simultaneous_peers: u32,
+        peer_refresh_interval: TimeDiff,
+        address_retry_interval: TimeDiff,
+        address_max_age: TimeDiff,
+        rng: &mut NodeRng,
+    ) -> Self {
+        let num_slots = (max_simultaneous_peers * SAMPLING_VIEW_SLOTS_PER_PEER) as usize;
         PeerList {
             peer_list: BTreeMap::new(),
+            sampling_view: SamplingView::new(num_slots, rng),
             keep_fresh: Timestamp::now(),
             max_simultaneous_peers,
             peer_refresh_interval,
+            address_retry_interval,
+            address_max_age,
         }
     }
+
+    /// The non-dishonest peers eligible to be sampling-view candidates.
+    fn candidates(&self) -> Vec<NodeId> {
+        self.peer_list
+            .iter()
+            .filter(|(_peer, reputation)| !reputation.banned)
+            .map(|(peer, _reputation)| *peer)
+            .collect()
+    }
+
     pub(super) fn register_peer(&mut self, peer: NodeId) {
         if self.peer_list.contains_key(&peer) {
             return;
         }
-        self.peer_list.insert(peer, PeerQuality::Unknown);
+        self.peer_list.insert(peer, PeerReputation::new());
+        self.sampling_view.consider_candidate(peer);
         self.keep_fresh = Timestamp::now();
     }
 
     pub(super) fn dishonest_peers(&self) -> Vec<NodeId> {
         self.peer_list
             .iter()
-            .filter_map(|(node_id, pq)| {
-                if *pq == PeerQuality::Dishonest {
+            .filter_map(|(node_id, reputation)| {
+                if reputation.banned {
                     Some(*node_id)
                 } else {
                     None
@@ -66,37 +348,32 @@ impl PeerList {
     }
 
     pub(super) fn flush_dishonest_peers(&mut self) {
-        self.peer_list.retain(|_, v| *v != PeerQuality::Dishonest);
+        self.peer_list.retain(|_, reputation| !reputation.banned);
     }
 
     pub(super) fn disqualify_peer(&mut self, peer: Option<NodeId>) {
         if let Some(peer_id) = peer {
-            self.peer_list.insert(peer_id, PeerQuality::Dishonest);
+            self.peer_list
+                .entry(peer_id)
+                .or_insert_with(PeerReputation::new)
+                .ban();
+            let candidates = self.candidates();
+            self.sampling_view.evict(peer_id, &candidates);
         }
     }
 
     pub(super) fn promote_peer(&mut self, peer: Option<NodeId>) {
         if let Some(peer_id) = peer {
             debug!("BlockSynchronizer: promoting peer {:?}", peer_id);
-            // vacant should be unreachable
             match self.peer_list.entry(peer_id) {
-                Entry::Vacant(_) => {
-                    self.peer_list.insert(peer_id, PeerQuality::Unknown);
+                Entry::Vacant(entry) => {
+                    let mut reputation = PeerReputation::new();
+                    reputation.reward();
+                    entry.insert(reputation);
+                }
+                Entry::Occupied(mut entry) => {
+                    entry.get_mut().reward();
                 }
-                Entry::Occupied(entry) => match entry.get() {
-                    PeerQuality::Dishonest => {
-                        // no change -- this is terminal
-                    }
-                    PeerQuality::Unknown => {
-                        self.peer_list.insert(peer_id, PeerQuality::Unreliable);
-                    }
-                    PeerQuality::Unreliable => {
-                        self.peer_list.insert(peer_id, PeerQuality::Reliable);
-                    }
-                    PeerQuality::Reliable => {
-                        // no change -- this is the best
-                    }
-                },
             }
         }
     }
@@ -104,27 +381,72 @@ impl PeerList {
     pub(super) fn demote_peer(&mut self, peer: Option<NodeId>) {
         if let Some(peer_id) = peer {
             debug!("BlockSynchronizer: demoting peer {:?}", peer_id);
-            // vacant should be unreachable
             match self.peer_list.entry(peer_id) {
                 Entry::Vacant(_) => {
-                    // no change
+                    // no change -- vacant should be unreachable
+                }
+                Entry::Occupied(mut entry) => {
+                    entry.get_mut().penalize();
+                }
+            }
+        }
+    }
+
+    pub(super) fn register_response_time(&mut self, peer: NodeId, response_time: TimeDiff) {
+        if let Entry::Occupied(mut entry) = self.peer_list.entry(peer) {
+            entry.get_mut().register_response_time(response_time);
+        }
+    }
+
+    /// Records the capabilities a peer has advertised, e.g. via a gossiped address message.
+    pub(super) fn register_capabilities(&mut self, peer: NodeId, capabilities: NodeCapabilities) {
+        self.peer_list
+            .entry(peer)
+            .or_insert_with(PeerReputation::new)
+            .capabilities = capabilities;
+    }
+
+    /// Records or refreshes an endpoint a peer has been observed at.
+    pub(super) fn register_address(&mut self, peer: NodeId, addr: SocketAddr) {
+        self.peer_list
+            .entry(peer)
+            .or_insert_with(PeerReputation::new)
+            .register_address(addr);
+    }
+
+    /// Returns candidate `(NodeId, SocketAddr)` endpoints worth retrying: addresses whose last
+    /// attempt (or last successful observation, if never attempted) is at least
+    /// `address_retry_interval` old, but not yet `address_max_age`, dropping anything older
+    /// than `address_max_age` along the way. This lets the synchronizer recover connectivity to
+    /// a flapping peer through an alternate endpoint instead of treating a single dead address
+    /// as a dead node. Tracking the attempt time separately from `last_seen` ensures a failed
+    /// reconnect still backs off for `address_retry_interval` instead of being handed out again
+    /// on every call.
+    pub(super) fn addresses_to_try(&mut self) -> Vec<(NodeId, SocketAddr)> {
+        let now = Timestamp::now();
+        let retry_interval = self.address_retry_interval;
+        let max_age = self.address_max_age;
+
+        let mut to_try = Vec::new();
+        for (node_id, reputation) in self.peer_list.iter_mut() {
+            if reputation.banned {
+                continue;
+            }
+            reputation
+                .addresses
+                .retain(|entry| now.saturating_diff(entry.last_seen) < max_age);
+            for entry in reputation.addresses.iter_mut() {
+                let last_checked = entry.last_attempted.unwrap_or(entry.last_seen);
+                if now.saturating_diff(last_checked) >= retry_interval {
+                    entry.last_attempted = Some(now);
+                    to_try.push((*node_id, entry.addr));
                 }
-                Entry::Occupied(entry) => match entry.get() {
-                    PeerQuality::Dishonest | PeerQuality::Unknown => {
-                        // no change
-                    }
-                    PeerQuality::Unreliable => {
-                        self.peer_list.insert(peer_id, PeerQuality::Unknown);
-                    }
-                    PeerQuality::Reliable => {
-                        self.peer_list.insert(peer_id, PeerQuality::Unreliable);
-                    }
-                },
             }
         }
+        to_try
     }
 
-    pub(super) fn need_peers(&mut self) -> PeersStatus {
+    pub(super) fn need_peers(&mut self, rng: &mut NodeRng) -> PeersStatus {
         if self.peer_list.is_empty() {
             debug!("PeerList: is empty");
             return PeersStatus::Insufficient;
@@ -133,10 +455,13 @@ impl PeerList {
         // periodically ask for refreshed peers
         if Timestamp::now().saturating_diff(self.keep_fresh) > self.peer_refresh_interval {
             self.keep_fresh = Timestamp::now();
+            let candidates = self.candidates();
+            self.sampling_view.churn(&candidates, rng);
+            let now = Timestamp::now();
             let count = self
                 .peer_list
-                .iter()
-                .filter(|(_, pq)| **pq == PeerQuality::Reliable || **pq == PeerQuality::Unknown)
+                .values()
+                .filter(|reputation| reputation.decayed_score(now) > REPUTATION_BASELINE)
                 .count();
             let reliability_goal = self.max_simultaneous_peers as usize;
             if count < reliability_goal {
@@ -148,34 +473,346 @@ impl PeerList {
         PeersStatus::Sufficient
     }
 
-    pub(super) fn qualified_peers(&self, rng: &mut NodeRng) -> Vec<NodeId> {
+    /// Returns up to `max_simultaneous_peers` qualified peers able to serve `required_capabilities`.
+    pub(super) fn qualified_peers(
+        &self,
+        rng: &mut NodeRng,
+        required_capabilities: NodeCapabilities,
+    ) -> Vec<NodeId> {
         let up_to = self.max_simultaneous_peers as usize;
+        let now = Timestamp::now();
+        let view_members: BTreeSet<NodeId> = self.sampling_view.occupants().collect();
 
-        // get most useful up to limit
-        let mut peers: Vec<NodeId> = self
+        // rank the sampling view's occupants by their decayed reputation score, highest first;
+        // drawing candidates from the view rather than the raw peer list keeps selection
+        // attack-resistant even if `register_peer` has been flooded
+        let ranked = self
             .peer_list
             .iter()
-            .filter(|(_peer, quality)| **quality == PeerQuality::Reliable)
-            .choose_multiple(rng, up_to)
-            .into_iter()
-            .map(|(peer, _)| *peer)
-            .collect();
-
-        // if below limit get semi-useful
-        let missing = up_to.saturating_sub(peers.len());
-        if missing > 0 {
-            let better_than_nothing = self
-                .peer_list
+            .filter(|(peer, reputation)| {
+                !reputation.banned
+                    && view_members.contains(peer)
+                    && reputation.capabilities.contains(required_capabilities)
+            })
+            .map(|(peer, reputation)| {
+                (*peer, reputation.decayed_score(now), reputation.selection_weight())
+            })
+            .sorted_by(|(_, left, _), (_, right, _)| right.total_cmp(left))
+            .collect_vec();
+
+        // take the top scorers, breaking ties within a reliability tier by sampling without
+        // replacement, weighted towards lower-latency peers rather than picking uniformly at
+        // random
+        let mut peers = Vec::with_capacity(up_to.min(ranked.len()));
+        let mut index = 0;
+        while index < ranked.len() && peers.len() < up_to {
+            let tier = reliability_tier(ranked[index].1);
+            let tied_end = ranked[index..]
+                .iter()
+                .take_while(|(_, score, _)| reliability_tier(*score) == tier)
+                .count()
+                + index;
+            let remaining = up_to - peers.len();
+            let tied = &ranked[index..tied_end];
+            let chosen = tied
+                .choose_multiple_weighted(rng, remaining, |(_, _, weight)| *weight)
+                .expect("selection weights are always finite and non-negative")
+                .map(|(peer, _, _)| *peer);
+            peers.extend(chosen);
+            index = tied_end;
+        }
+
+        peers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn decayed_score_halves_after_one_half_life() {
+        let mut reputation = PeerReputation::new();
+        reputation.score = 10.0;
+        let start = Timestamp::now();
+        reputation.last_updated = start;
+
+        let one_half_life_later =
+            start.saturating_add(TimeDiff::from_seconds(REPUTATION_HALF_LIFE_SECS as u32));
+        let decayed = reputation.decayed_score(one_half_life_later);
+
+        assert!(
+            (decayed - 5.0).abs() < 1e-6,
+            "score should have decayed exactly halfway back to the baseline after one \
+             half-life, got {decayed}"
+        );
+    }
+
+    #[test]
+    fn banned_peer_score_is_pinned_regardless_of_elapsed_time() {
+        let mut reputation = PeerReputation::new();
+        reputation.score = 100.0;
+        reputation.ban();
+
+        let just_after = reputation.last_updated;
+        assert_eq!(reputation.decayed_score(just_after), REPUTATION_BANNED);
+
+        let far_future = just_after.saturating_add(TimeDiff::from_seconds(u32::MAX));
+        assert_eq!(
+            reputation.decayed_score(far_future),
+            REPUTATION_BANNED,
+            "a banned peer must never decay back towards a usable score, regardless of how \
+             much time has elapsed"
+        );
+
+        reputation.reward();
+        reputation.penalize();
+        assert_eq!(
+            reputation.score, REPUTATION_BANNED,
+            "reward/penalize must not be able to lift a ban"
+        );
+    }
+
+    #[test]
+    fn slot_occupancy_is_independent_of_registration_order_under_flood() {
+        let mut rng = NodeRng::from_entropy();
+        let honest = NodeId::random(&mut rng);
+        let sybils: Vec<NodeId> = (0..50).map(|_| NodeId::random(&mut rng)).collect();
+        let seeds = [11u64, 22, 33, 44];
+
+        let mut honest_first = SamplingView {
+            slots: seeds
                 .iter()
-                .filter(|(_peer, quality)| {
-                    **quality == PeerQuality::Unreliable || **quality == PeerQuality::Unknown
+                .map(|&seed| SamplingSlot {
+                    seed,
+                    occupant: None,
                 })
-                .choose_multiple(rng, missing)
-                .into_iter()
-                .map(|(peer, _)| *peer);
+                .collect(),
+        };
+        honest_first.consider_candidate(honest);
+        for &sybil in &sybils {
+            honest_first.consider_candidate(sybil);
+        }
 
-            peers.extend(better_than_nothing);
+        let mut honest_last = SamplingView {
+            slots: seeds
+                .iter()
+                .map(|&seed| SamplingSlot {
+                    seed,
+                    occupant: None,
+                })
+                .collect(),
+        };
+        for &sybil in &sybils {
+            honest_last.consider_candidate(sybil);
         }
-        peers
+        honest_last.consider_candidate(honest);
+
+        let occupants_first = honest_first.occupants().collect_vec();
+        let occupants_last = honest_last.occupants().collect_vec();
+        assert_eq!(
+            occupants_first, occupants_last,
+            "slot occupancy must depend only on candidate identity, not on how many times or \
+             in what order a flood of Sybil candidates was registered"
+        );
+    }
+
+    #[test]
+    fn evict_only_reassigns_the_slots_the_evicted_peer_occupied() {
+        let mut rng = NodeRng::from_entropy();
+        let peer_a = NodeId::random(&mut rng);
+        let peer_b = NodeId::random(&mut rng);
+        let peer_c = NodeId::random(&mut rng);
+
+        let mut view = SamplingView::new(6, &mut rng);
+        for peer in [peer_a, peer_b, peer_c] {
+            view.consider_candidate(peer);
+        }
+        let occupants_before = view.occupants().collect_vec();
+
+        view.evict(peer_a, &[peer_b, peer_c]);
+
+        for (slot, before) in view.slots.iter().zip(occupants_before.into_iter()) {
+            assert_ne!(
+                slot.occupant,
+                Some(peer_a),
+                "the evicted peer must not remain in any slot"
+            );
+            if before == Some(peer_a) {
+                assert!(
+                    slot.occupant == Some(peer_b) || slot.occupant == Some(peer_c),
+                    "a slot the evicted peer occupied must be reassigned from the remaining \
+                     candidates"
+                );
+            } else {
+                assert_eq!(
+                    slot.occupant, before,
+                    "slots the evicted peer didn't occupy must be left untouched"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn churn_leaves_every_slot_on_its_hash_minimizing_candidate_for_an_unchanged_pool() {
+        let mut rng = NodeRng::from_entropy();
+        let candidates: Vec<NodeId> = (0..10).map(|_| NodeId::random(&mut rng)).collect();
+        let mut view = SamplingView::new(8, &mut rng);
+        for &candidate in &candidates {
+            view.consider_candidate(candidate);
+        }
+
+        view.churn(&candidates, &mut rng);
+
+        for slot in &view.slots {
+            let mut expected = SamplingSlot {
+                seed: slot.seed,
+                occupant: None,
+            };
+            expected.recompute(candidates.iter().copied());
+            assert_eq!(
+                slot.occupant, expected.occupant,
+                "every slot's occupant must be the current hash-minimizing candidate for its \
+                 seed after churn, whether or not that slot was re-seeded"
+            );
+        }
+    }
+
+    #[test]
+    fn reliability_tier_groups_nearby_scores_and_splits_distant_ones() {
+        assert_eq!(reliability_tier(0.0), reliability_tier(0.99));
+        assert_ne!(reliability_tier(0.99), reliability_tier(1.0));
+        assert_eq!(reliability_tier(-0.5), reliability_tier(-0.01));
+    }
+
+    #[test]
+    fn selection_weight_prefers_lower_latency_over_higher_latency() {
+        let mut low_latency = PeerReputation::new();
+        low_latency.register_response_time(TimeDiff::from_millis(10));
+
+        let mut high_latency = PeerReputation::new();
+        high_latency.register_response_time(TimeDiff::from_millis(1_000));
+
+        let mut never_measured = PeerReputation::new();
+
+        assert!(
+            low_latency.selection_weight() > high_latency.selection_weight(),
+            "a peer with a lower latency EWMA must carry a higher selection weight"
+        );
+        assert_eq!(
+            never_measured.selection_weight(),
+            1.0 / LATENCY_WEIGHT_CONSTANT_MILLIS,
+            "a peer with no latency samples yet must get the neutral weight"
+        );
+    }
+
+    #[test]
+    fn qualified_peers_excludes_peers_missing_required_capabilities() {
+        let mut rng = NodeRng::from_entropy();
+        let capable = NodeId::random(&mut rng);
+        let incapable = NodeId::random(&mut rng);
+
+        let mut peer_list = PeerList::new(
+            2,
+            TimeDiff::from_seconds(60),
+            TimeDiff::from_seconds(60),
+            TimeDiff::from_seconds(3600),
+            &mut rng,
+        );
+        peer_list.register_peer(capable);
+        peer_list.register_peer(incapable);
+        peer_list.register_capabilities(capable, NodeCapabilities::SERVES_TRIES);
+
+        // Force both peers into the sampling view deterministically, independent of the
+        // hash-minimization outcome, so the test exercises only the capability filter.
+        peer_list.sampling_view = SamplingView {
+            slots: vec![
+                SamplingSlot {
+                    seed: 0,
+                    occupant: Some(capable),
+                },
+                SamplingSlot {
+                    seed: 1,
+                    occupant: Some(incapable),
+                },
+            ],
+        };
+
+        let qualified = peer_list.qualified_peers(&mut rng, NodeCapabilities::SERVES_TRIES);
+
+        assert_eq!(
+            qualified,
+            vec![capable],
+            "qualified_peers must exclude peers that haven't advertised the required \
+             capabilities, even when they're present in the sampling view"
+        );
+    }
+
+    #[test]
+    fn addresses_to_try_backs_off_after_being_handed_out_once() {
+        let mut rng = NodeRng::from_entropy();
+        let peer = NodeId::random(&mut rng);
+        let retry_interval = TimeDiff::from_seconds(60);
+        let mut peer_list = PeerList::new(
+            1,
+            TimeDiff::from_seconds(3600),
+            retry_interval,
+            TimeDiff::from_seconds(3600),
+            &mut rng,
+        );
+        peer_list.register_peer(peer);
+        peer_list.register_address(peer, "127.0.0.1:1234".parse().unwrap());
+
+        // Back-date the address past the retry interval so it's eligible on the first poll.
+        if let Some(reputation) = peer_list.peer_list.get_mut(&peer) {
+            for entry in reputation.addresses.iter_mut() {
+                entry.last_seen = Timestamp::now().saturating_sub(retry_interval);
+            }
+        }
+
+        let first = peer_list.addresses_to_try();
+        assert_eq!(
+            first.len(),
+            1,
+            "an address past the retry interval should be offered"
+        );
+
+        let second = peer_list.addresses_to_try();
+        assert!(
+            second.is_empty(),
+            "an address that was just handed out must not be offered again before another \
+             retry interval elapses, even though a failed reconnect never advances last_seen"
+        );
+    }
+
+    #[test]
+    fn addresses_to_try_excludes_banned_peers() {
+        let mut rng = NodeRng::from_entropy();
+        let peer = NodeId::random(&mut rng);
+        let retry_interval = TimeDiff::from_seconds(60);
+        let mut peer_list = PeerList::new(
+            1,
+            TimeDiff::from_seconds(3600),
+            retry_interval,
+            TimeDiff::from_seconds(3600),
+            &mut rng,
+        );
+        peer_list.register_peer(peer);
+        peer_list.register_address(peer, "127.0.0.1:1234".parse().unwrap());
+        if let Some(reputation) = peer_list.peer_list.get_mut(&peer) {
+            for entry in reputation.addresses.iter_mut() {
+                entry.last_seen = Timestamp::now().saturating_sub(retry_interval);
+            }
+        }
+
+        peer_list.disqualify_peer(Some(peer));
+
+        assert!(
+            peer_list.addresses_to_try().is_empty(),
+            "a banned peer's addresses must never be offered for retry, since the ban is meant \
+             to be terminal"
+        );
     }
 }
@@ -1,31 +1,110 @@
 use std::{
+    collections::BTreeMap,
     fmt::{self, Display, Formatter},
     net::SocketAddr,
+    ops::BitOr,
 };
 
 use datasize::DataSize;
+use rand::seq::{IteratorRandom, SliceRandom};
 use serde::{Deserialize, Serialize};
 
+use casper_types::{ProtocolVersion, U512};
+
 use crate::{
     effect::GossipTarget,
-    types::{GossiperItem, Item},
+    types::{GossiperItem, Item, NodeId},
+    NodeRng,
 };
 
-/// Used to gossip our public listening address to peers.
+/// Bitflags describing what data and protocol surface a node advertises it can serve.
+///
+/// Receivers use these to avoid asking a peer for something it has already said it can't
+/// provide, e.g. historical tries from a pruned node.
+#[derive(
+    Copy,
+    Clone,
+    DataSize,
+    Default,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    Serialize,
+    Deserialize,
+    Debug,
+)]
+pub struct NodeCapabilities(u8);
+
+impl NodeCapabilities {
+    /// The node retains and serves historical global state.
+    pub const SERVES_HISTORICAL_STATE: NodeCapabilities = NodeCapabilities(0b0000_0001);
+    /// The node retains and serves historical tries.
+    pub const SERVES_TRIES: NodeCapabilities = NodeCapabilities(0b0000_0010);
+    /// The node can answer sync-leap requests.
+    pub const SERVES_SYNC_LEAP: NodeCapabilities = NodeCapabilities(0b0000_0100);
+
+    /// No advertised capabilities.
+    pub const fn none() -> Self {
+        NodeCapabilities(0)
+    }
+
+    /// Returns `true` if `self` advertises every capability set in `other`.
+    pub const fn contains(self, other: Self) -> bool {
+        (self.0 & other.0) == other.0
+    }
+}
+
+impl BitOr for NodeCapabilities {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        NodeCapabilities(self.0 | rhs.0)
+    }
+}
+
+/// Used to gossip our public listening address, protocol version range and advertised
+/// capabilities to peers.
 #[derive(
     Copy, Clone, DataSize, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug,
 )]
-pub struct GossipedAddress(SocketAddr);
+pub struct GossipedAddress {
+    address: SocketAddr,
+    /// The inclusive range of protocol versions this node is able to speak.
+    protocol_version_range: (ProtocolVersion, ProtocolVersion),
+    capabilities: NodeCapabilities,
+}
 
 impl GossipedAddress {
-    pub(super) fn new(address: SocketAddr) -> Self {
-        GossipedAddress(address)
+    pub(super) fn new(
+        address: SocketAddr,
+        protocol_version_range: (ProtocolVersion, ProtocolVersion),
+        capabilities: NodeCapabilities,
+    ) -> Self {
+        GossipedAddress {
+            address,
+            protocol_version_range,
+            capabilities,
+        }
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    pub fn protocol_version_range(&self) -> (ProtocolVersion, ProtocolVersion) {
+        self.protocol_version_range
+    }
+
+    pub fn capabilities(&self) -> NodeCapabilities {
+        self.capabilities
     }
 }
 
 impl Display for GossipedAddress {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
-        write!(formatter, "gossiped-address {}", self.0)
+        write!(formatter, "gossiped-address {}", self.address)
     }
 }
 
@@ -42,12 +121,144 @@ impl GossiperItem for GossipedAddress {
     const REQUIRES_GOSSIP_RECEIVED_ANNOUNCEMENT: bool = false;
 
     fn target(&self) -> GossipTarget {
-        GossipTarget::All
+        GossipTarget::StakeWeighted
+    }
+}
+
+/// Converts a validator's stake into an `f64` selection weight.
+///
+/// `U512` stores its value as eight little-endian `u64` limbs; folding them from the most to
+/// the least significant recovers an (approximate, for extreme values) floating-point weight,
+/// which is all a proportional sampler needs.
+fn stake_weight(stake: U512) -> f64 {
+    const LIMB_RADIX: f64 = 18_446_744_073_709_551_616.0; // 2^64
+    stake
+        .0
+        .iter()
+        .rev()
+        .fold(0.0, |acc, limb| acc * LIMB_RADIX + *limb as f64)
+}
+
+/// Samples a fan-out set of peers for stake-weighted, layered gossip: validators are sampled
+/// with probability proportional to their stake (layer 1) before falling back to a uniform
+/// sample of the stake-agnostic long tail (layer 2). This way economically-important nodes
+/// converge on newly-gossiped items first, and an attacker running many zero-stake nodes
+/// cannot absorb a disproportionate share of the fan-out.
+pub(crate) fn select_stake_weighted_targets(
+    candidates: &[NodeId],
+    validator_stakes: &BTreeMap<NodeId, U512>,
+    fanout: usize,
+    rng: &mut NodeRng,
+) -> Vec<NodeId> {
+    let (validators, long_tail): (Vec<NodeId>, Vec<NodeId>) = candidates
+        .iter()
+        .copied()
+        .partition(|candidate| validator_stakes.contains_key(candidate));
+
+    let mut targets = Vec::with_capacity(fanout.min(candidates.len()));
+
+    // Layer 1: high-stake validators, sampled proportional to stake. If every candidate
+    // validator has zero recorded stake (e.g. a bootstrapping stake table), the weighted draw
+    // has nothing to weight by and errors out; fall back to a uniform sample so known
+    // validators are still included rather than silently dropped from the fan-out.
+    let validator_quota = fanout.min(validators.len());
+    match validators.choose_multiple_weighted(rng, validator_quota, |validator| {
+        validator_stakes
+            .get(validator)
+            .copied()
+            .map(stake_weight)
+            .unwrap_or(0.0)
+    }) {
+        Ok(chosen) => targets.extend(chosen),
+        Err(_) => {
+            targets.extend(validators.iter().copied().choose_multiple(rng, validator_quota))
+        }
     }
+
+    // Layer 2: once layer 1 is saturated, fall back to the long tail so the item still
+    // reaches non-validating peers.
+    let missing = fanout.saturating_sub(targets.len());
+    if missing > 0 {
+        targets.extend(long_tail.into_iter().choose_multiple(rng, missing));
+    }
+
+    targets
 }
 
 impl From<GossipedAddress> for SocketAddr {
     fn from(gossiped_address: GossipedAddress) -> Self {
-        gossiped_address.0
+        gossiped_address.address
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn select_stake_weighted_targets_includes_validators_even_with_zero_stake() {
+        let mut rng = NodeRng::from_entropy();
+        let validators: Vec<NodeId> = (0..3).map(|_| NodeId::random(&mut rng)).collect();
+        let validator_stakes: BTreeMap<NodeId, U512> =
+            validators.iter().map(|&validator| (validator, U512::zero())).collect();
+
+        let chosen = select_stake_weighted_targets(&validators, &validator_stakes, 3, &mut rng);
+
+        assert_eq!(
+            chosen.len(),
+            3,
+            "validators must still be included in the fan-out even when every one of them has \
+             zero recorded stake"
+        );
+    }
+
+    #[test]
+    fn select_stake_weighted_targets_prefers_the_single_staked_validator() {
+        let mut rng = NodeRng::from_entropy();
+        let staked = NodeId::random(&mut rng);
+        let unstaked: Vec<NodeId> = (0..5).map(|_| NodeId::random(&mut rng)).collect();
+
+        let mut validator_stakes = BTreeMap::new();
+        validator_stakes.insert(staked, U512::from(1_000_000u64));
+        for &validator in &unstaked {
+            validator_stakes.insert(validator, U512::zero());
+        }
+
+        let mut candidates = unstaked.clone();
+        candidates.push(staked);
+
+        let chosen = select_stake_weighted_targets(&candidates, &validator_stakes, 1, &mut rng);
+
+        assert_eq!(
+            chosen,
+            vec![staked],
+            "the only validator with nonzero stake should always win a fan-out of size 1"
+        );
+    }
+
+    #[test]
+    fn select_stake_weighted_targets_falls_back_to_the_long_tail_once_validators_are_exhausted() {
+        let mut rng = NodeRng::from_entropy();
+        let validator = NodeId::random(&mut rng);
+        let long_tail: Vec<NodeId> = (0..5).map(|_| NodeId::random(&mut rng)).collect();
+
+        let mut validator_stakes = BTreeMap::new();
+        validator_stakes.insert(validator, U512::from(1u64));
+
+        let mut candidates = long_tail.clone();
+        candidates.push(validator);
+
+        let chosen = select_stake_weighted_targets(&candidates, &validator_stakes, 3, &mut rng);
+
+        assert_eq!(chosen.len(), 3);
+        assert!(chosen.contains(&validator));
+        assert_eq!(
+            chosen.iter().filter(|peer| long_tail.contains(peer)).count(),
+            2,
+            "once the single validator is included, the remaining fan-out slots must be filled \
+             from the stake-agnostic long tail"
+        );
     }
 }
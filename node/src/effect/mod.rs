@@ -0,0 +1,37 @@
+use std::collections::BTreeMap;
+
+use rand::seq::IteratorRandom;
+
+use casper_types::U512;
+
+use crate::{
+    components::network::gossiped_address::select_stake_weighted_targets, types::NodeId, NodeRng,
+};
+
+/// Describes which peers a gossiped item should be propagated to next.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum GossipTarget {
+    /// Propagate to a uniformly random sample of all known peers.
+    All,
+    /// Propagate to high-stake validators first, falling back to the stake-agnostic long tail
+    /// only once they've been covered.
+    StakeWeighted,
+}
+
+impl GossipTarget {
+    /// Resolves this target into the concrete set of peers to gossip to next.
+    pub fn resolve(
+        &self,
+        candidates: &[NodeId],
+        validator_stakes: &BTreeMap<NodeId, U512>,
+        fanout: usize,
+        rng: &mut NodeRng,
+    ) -> Vec<NodeId> {
+        match self {
+            GossipTarget::All => candidates.iter().copied().choose_multiple(rng, fanout),
+            GossipTarget::StakeWeighted => {
+                select_stake_weighted_targets(candidates, validator_stakes, fanout, rng)
+            }
+        }
+    }
+}